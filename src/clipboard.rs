@@ -0,0 +1,62 @@
+/// Where [`egui`] copies text to and pastes text from
+///
+/// The [`Gui`](crate::Gui) uses a [`MemoryClipboard`] by default, to use the system clipboard
+/// enable the `clipboard` feature and set a [`SystemClipboard`] with
+/// [`Gui::set_clipboard`](crate::Gui::set_clipboard)
+pub trait Clipboard {
+    /// Returns the text in the clipboard, if there is any
+    fn get(&mut self) -> Option<String>;
+
+    /// Replaces the text in the clipboard
+    fn set(&mut self, text: String);
+}
+
+/// A [`Clipboard`] that only lives in memory, it is not shared with other applications
+#[derive(Default, Clone, Debug)]
+pub struct MemoryClipboard {
+    text: Option<String>,
+}
+
+impl Clipboard for MemoryClipboard {
+    fn get(&mut self) -> Option<String> {
+        self.text.clone()
+    }
+
+    fn set(&mut self, text: String) {
+        self.text = Some(text);
+    }
+}
+
+/// A [`Clipboard`] backed by the clipboard of the system
+#[cfg(feature = "clipboard")]
+pub struct SystemClipboard {
+    clipboard: arboard::Clipboard,
+}
+
+#[cfg(feature = "clipboard")]
+impl SystemClipboard {
+    pub fn new() -> Result<Self, arboard::Error> {
+        Ok(Self {
+            clipboard: arboard::Clipboard::new()?,
+        })
+    }
+}
+
+#[cfg(feature = "clipboard")]
+impl Clipboard for SystemClipboard {
+    fn get(&mut self) -> Option<String> {
+        match self.clipboard.get_text() {
+            Ok(text) => Some(text),
+            Err(err) => {
+                eprintln!("Failed to read the clipboard: {err}");
+                None
+            }
+        }
+    }
+
+    fn set(&mut self, text: String) {
+        if let Err(err) = self.clipboard.set_text(text) {
+            eprintln!("Failed to write to the clipboard: {err}");
+        }
+    }
+}
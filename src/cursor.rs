@@ -0,0 +1,96 @@
+use winit::window::CursorIcon;
+
+/// Translates an [`egui::CursorIcon`] into the icon of the window, `None` means the cursor is hidden
+#[inline]
+pub(crate) fn translate_cursor_icon(cursor_icon: egui::CursorIcon) -> Option<CursorIcon> {
+    Some(match cursor_icon {
+        egui::CursorIcon::None => {
+            return None;
+        }
+
+        egui::CursorIcon::Default => CursorIcon::Default,
+        egui::CursorIcon::ContextMenu => CursorIcon::ContextMenu,
+        egui::CursorIcon::Help => CursorIcon::Help,
+        egui::CursorIcon::PointingHand => CursorIcon::Pointer,
+        egui::CursorIcon::Progress => CursorIcon::Progress,
+        egui::CursorIcon::Wait => CursorIcon::Wait,
+
+        egui::CursorIcon::Cell => CursorIcon::Cell,
+        egui::CursorIcon::Crosshair => CursorIcon::Crosshair,
+        egui::CursorIcon::Text => CursorIcon::Text,
+        egui::CursorIcon::VerticalText => CursorIcon::VerticalText,
+
+        egui::CursorIcon::Alias => CursorIcon::Alias,
+        egui::CursorIcon::Copy => CursorIcon::Copy,
+        egui::CursorIcon::Move => CursorIcon::Move,
+        egui::CursorIcon::NoDrop => CursorIcon::NoDrop,
+        egui::CursorIcon::NotAllowed => CursorIcon::NotAllowed,
+        egui::CursorIcon::Grab => CursorIcon::Grab,
+        egui::CursorIcon::Grabbing => CursorIcon::Grabbing,
+
+        egui::CursorIcon::AllScroll => CursorIcon::AllScroll,
+
+        egui::CursorIcon::ResizeHorizontal => CursorIcon::EwResize,
+        egui::CursorIcon::ResizeNeSw => CursorIcon::NeswResize,
+        egui::CursorIcon::ResizeNwSe => CursorIcon::NwseResize,
+        egui::CursorIcon::ResizeVertical => CursorIcon::NsResize,
+
+        egui::CursorIcon::ResizeEast => CursorIcon::EResize,
+        egui::CursorIcon::ResizeSouthEast => CursorIcon::SeResize,
+        egui::CursorIcon::ResizeSouth => CursorIcon::SResize,
+        egui::CursorIcon::ResizeSouthWest => CursorIcon::SwResize,
+        egui::CursorIcon::ResizeWest => CursorIcon::WResize,
+        egui::CursorIcon::ResizeNorthWest => CursorIcon::NwResize,
+        egui::CursorIcon::ResizeNorth => CursorIcon::NResize,
+        egui::CursorIcon::ResizeNorthEast => CursorIcon::NeResize,
+
+        egui::CursorIcon::ResizeColumn => CursorIcon::ColResize,
+        egui::CursorIcon::ResizeRow => CursorIcon::RowResize,
+
+        egui::CursorIcon::ZoomIn => CursorIcon::ZoomIn,
+        egui::CursorIcon::ZoomOut => CursorIcon::ZoomOut,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn none_hides_the_cursor() {
+        assert_eq!(translate_cursor_icon(egui::CursorIcon::None), None);
+    }
+
+    #[test]
+    fn every_other_icon_is_shown() {
+        for icon in egui::CursorIcon::ALL {
+            if icon != egui::CursorIcon::None {
+                assert!(translate_cursor_icon(icon).is_some(), "{icon:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn renamed_icons() {
+        for (icon, expected) in [
+            (egui::CursorIcon::Default, CursorIcon::Default),
+            (egui::CursorIcon::PointingHand, CursorIcon::Pointer),
+            (egui::CursorIcon::ResizeHorizontal, CursorIcon::EwResize),
+            (egui::CursorIcon::ResizeVertical, CursorIcon::NsResize),
+            (egui::CursorIcon::ResizeNeSw, CursorIcon::NeswResize),
+            (egui::CursorIcon::ResizeNwSe, CursorIcon::NwseResize),
+            (egui::CursorIcon::ResizeEast, CursorIcon::EResize),
+            (egui::CursorIcon::ResizeSouthEast, CursorIcon::SeResize),
+            (egui::CursorIcon::ResizeSouth, CursorIcon::SResize),
+            (egui::CursorIcon::ResizeSouthWest, CursorIcon::SwResize),
+            (egui::CursorIcon::ResizeWest, CursorIcon::WResize),
+            (egui::CursorIcon::ResizeNorthWest, CursorIcon::NwResize),
+            (egui::CursorIcon::ResizeNorth, CursorIcon::NResize),
+            (egui::CursorIcon::ResizeNorthEast, CursorIcon::NeResize),
+            (egui::CursorIcon::ResizeColumn, CursorIcon::ColResize),
+            (egui::CursorIcon::ResizeRow, CursorIcon::RowResize),
+        ] {
+            assert_eq!(translate_cursor_icon(icon), Some(expected), "{icon:?}");
+        }
+    }
+}
@@ -1,8 +1,35 @@
-use std::time::Instant;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
-use egui::{pos2, vec2, Key, PointerButton, Pos2, RawInput};
-use winit::keyboard::{KeyCode, ModifiersState};
-use winit::{event::MouseButton, keyboard::PhysicalKey};
+use egui::{vec2, Key, PointerButton, Pos2, RawInput};
+use ggez::glam::{vec3, Mat4, Vec2};
+use ggez::graphics::Rect;
+use winit::event::{Ime, MouseButton, Touch};
+use winit::keyboard::{KeyCode, ModifiersState, PhysicalKey};
+
+use crate::clipboard::{Clipboard, MemoryClipboard};
+use crate::units::{pixels_to_points, points_to_pixels};
+
+const MIN_ZOOM_FACTOR: f32 = 0.2;
+const MAX_ZOOM_FACTOR: f32 = 5.0;
+
+/// How the ui is fitted in the window
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Scaling {
+    /// The ui fills the window, at the scale factor of the window
+    #[default]
+    Window,
+    /// The ui is laid out at a fixed resolution, in points, and scaled to fit in the viewport
+    /// keeping its aspect ratio, like a game drawn with
+    /// [`Canvas::set_screen_coordinates`](ggez::graphics::Canvas::set_screen_coordinates).
+    /// The viewport is a rect of the window in pixels, with `None` the ui is letterboxed in the
+    /// whole window
+    Reference {
+        resolution: (f32, f32),
+        viewport: Option<Rect>,
+    },
+}
 
 /// Contains and manages everything related to the [`egui`] input
 ///
@@ -10,18 +37,55 @@ use winit::{event::MouseButton, keyboard::PhysicalKey};
 pub struct Input {
     dt: Instant,
     pointer_pos: Pos2,
+    mouse_pos: Option<Pos2>,
+    window: Option<(f32, (f32, f32))>,
+    screen_size: Option<(f32, f32)>,
+    target_size: Option<(f32, f32)>,
+    pointer_inside: bool,
+    pointer_touch_id: Option<u64>,
+    touches: HashMap<u64, Pos2>,
+    pinch_distance: Option<f32>,
+    zoom_sensitivity: f32,
+    held_keys: HashMap<PhysicalKey, Instant>,
+    ime_composing: bool,
+    key_repeat: Option<(Duration, Duration)>,
+    pub(crate) clipboard: Arc<Mutex<dyn Clipboard>>,
     pub(crate) raw: RawInput,
     pub(crate) scale_factor: f32,
+    pub(crate) zoom_factor: f32,
+    scaling: Scaling,
+    zoom_shortcuts: bool,
+    pub(crate) transform: Mat4,
+    pub(crate) pointer_bounds: Option<egui::Rect>,
 }
 
 impl Default for Input {
-    /// scale_factor: 1.0
+    /// scale_factor: 1.0, zoom_factor: 1.0 with the zoom shortcuts enabled, zoom sensitivity: 0.1,
+    /// key repeat: 500ms delay and 30ms interval
     fn default() -> Self {
         Self {
             dt: Instant::now(),
             pointer_pos: Default::default(),
+            mouse_pos: None,
+            window: None,
+            screen_size: None,
+            target_size: None,
+            pointer_inside: true,
+            pointer_touch_id: None,
+            touches: Default::default(),
+            pinch_distance: None,
+            zoom_sensitivity: 0.1,
+            held_keys: Default::default(),
+            ime_composing: false,
+            key_repeat: Some((Duration::from_millis(500), Duration::from_millis(30))),
+            clipboard: Arc::new(Mutex::new(MemoryClipboard::default())),
             raw: Default::default(),
             scale_factor: 1.0,
+            zoom_factor: 1.0,
+            scaling: Scaling::Window,
+            zoom_shortcuts: true,
+            transform: Mat4::IDENTITY,
+            pointer_bounds: None,
         }
     }
 }
@@ -37,48 +101,124 @@ impl Input {
     /// the information of the pressed characters, to update that information you have to
     /// use the function [text_input_event](Input:: text_input_event)
     pub fn update(&mut self, ctx: &ggez::Context) {
-        /*======================= Keyboard =======================*/
-        for key in ctx.keyboard.pressed_physical_keys.iter() {
-            if ctx.keyboard.is_physical_key_just_pressed(key) {
+        self.update_window(ctx);
+        // keys are only tracked while the window has focus, so they can't get stuck
+        if self.raw.focused {
+            self.update_keyboard(ctx);
+        }
+        self.update_mouse(ctx);
+    }
+
+    /// Follows the size and the scale factor of the window, only when they change so a scale
+    /// factor set by hand is kept until then
+    pub(crate) fn update_window(&mut self, ctx: &ggez::Context) {
+        let size = self.target_size.unwrap_or_else(|| ctx.gfx.drawable_size());
+        let window = (ctx.gfx.window().scale_factor() as f32, size);
+        if self.window.replace(window) != Some(window) {
+            let (scale_factor, size) = window;
+            self.set_scale_factor(scale_factor, size);
+        }
+    }
+
+    fn update_keyboard(&mut self, ctx: &ggez::Context) {
+        let modifiers = translate_modifier(ctx.keyboard.active_modifiers);
+        let now = Instant::now();
+        self.raw.modifiers = modifiers;
+
+        // keys released since the last update
+        let pressed_keys = &ctx.keyboard.pressed_physical_keys;
+        self.held_keys.retain(|key, _| {
+            let held = pressed_keys.contains(key);
+            if !held {
                 if let Some(key) = translate_physical_key(*key) {
                     self.raw.events.push(egui::Event::Key {
                         key,
-                        pressed: true,
+                        pressed: false,
                         repeat: false,
-                        modifiers: translate_modifier(ctx.keyboard.active_modifiers),
+                        modifiers,
                     })
                 }
             }
+            held
+        });
+
+        // keys pressed since the last update, and the ones held long enough to repeat
+        for physical_key in pressed_keys.iter() {
+            let repeat = match self.held_keys.get_mut(physical_key) {
+                None => {
+                    let delay = self.key_repeat.map_or(Duration::ZERO, |(delay, _)| delay);
+                    self.held_keys.insert(*physical_key, now + delay);
+                    false
+                }
+                Some(next_repeat) => match self.key_repeat {
+                    Some((_, interval)) if *next_repeat <= now => {
+                        *next_repeat = now + interval;
+                        true
+                    }
+                    _ => continue,
+                },
+            };
+            if let Some(key) = translate_physical_key(*physical_key) {
+                if self.zoom_shortcut(key, modifiers) {
+                    continue;
+                }
+                if let Some(event) = self.clipboard_event(key, modifiers) {
+                    self.raw.events.push(event);
+                }
+                self.raw.events.push(egui::Event::Key {
+                    key,
+                    pressed: true,
+                    repeat,
+                    modifiers,
+                })
+            }
         }
+    }
 
-        /*======================= Mouse =======================*/
+    fn update_mouse(&mut self, ctx: &ggez::Context) {
+        // while a finger is acting as the pointer the mouse is ignored
         let ggez::mint::Point2 { x, y } = ctx.mouse.position();
-        self.pointer_pos = pos2(x / self.scale_factor, y / self.scale_factor);
-        self.raw
-            .events
-            .push(egui::Event::PointerMoved(self.pointer_pos));
+        let mapped = self.screen_to_ui(x, y);
+        if let Some(mouse_pos) = mapped {
+            let was_inside = self.pointer_inside;
+            self.pointer_inside = self
+                .pointer_bounds
+                .is_none_or(|bounds| bounds.contains(mouse_pos));
+            if self.mouse_pos.replace(mouse_pos) != Some(mouse_pos)
+                && self.pointer_touch_id.is_none()
+            {
+                if self.pointer_inside {
+                    self.pointer_pos = mouse_pos;
+                    self.raw
+                        .events
+                        .push(egui::Event::PointerMoved(self.pointer_pos));
+                } else if was_inside {
+                    self.raw.events.push(egui::Event::PointerGone);
+                }
+            }
+        }
 
-        for button in [MouseButton::Left, MouseButton::Middle, MouseButton::Right] {
-            if ctx.mouse.button_just_pressed(button) {
+        for button in [
+            MouseButton::Left,
+            MouseButton::Middle,
+            MouseButton::Right,
+            MouseButton::Back,
+            MouseButton::Forward,
+        ] {
+            let Some(pointer_button) = translate_mouse_button(button) else {
+                continue;
+            };
+            // presses outside of the bounds are ignored, releases are not so drags can end
+            if ctx.mouse.button_just_pressed(button) && mapped.is_some() && self.pointer_inside {
                 self.raw.events.push(egui::Event::PointerButton {
-                    button: match button {
-                        MouseButton::Left => PointerButton::Primary,
-                        MouseButton::Right => PointerButton::Secondary,
-                        MouseButton::Middle => PointerButton::Middle,
-                        _ => unreachable!(),
-                    },
+                    button: pointer_button,
                     pos: self.pointer_pos,
                     pressed: true,
                     modifiers: translate_modifier(ctx.keyboard.active_modifiers),
                 });
             } else if ctx.mouse.button_just_released(button) {
                 self.raw.events.push(egui::Event::PointerButton {
-                    button: match button {
-                        MouseButton::Left => PointerButton::Primary,
-                        MouseButton::Right => PointerButton::Secondary,
-                        MouseButton::Middle => PointerButton::Middle,
-                        _ => unreachable!(),
-                    },
+                    button: pointer_button,
                     pos: self.pointer_pos,
                     pressed: false,
                     modifiers: translate_modifier(ctx.keyboard.active_modifiers),
@@ -87,20 +227,230 @@ impl Input {
         }
     }
 
-    /// Set the scale_factor and update the screen_rect
+    /// Maps a position in the window, in pixels, to the position in the ui, in points, undoing
+    /// the [`DrawParam`](ggez::graphics::DrawParam) the gui was drawn with, `None` when that
+    /// transform can't be undone, e.g. while it scales the gui to nothing
+    fn screen_to_ui(&self, x: f32, y: f32) -> Option<Pos2> {
+        if self.transform.determinant() == 0.0 {
+            return None;
+        }
+        let pos = self.transform.inverse().transform_point3(vec3(x, y, 0.0));
+        Some(pixels_to_points(vec2(pos.x, pos.y), self.pixels_per_point()).to_pos2())
+    }
+
+    /// Lays out the ui for a target of `size` pixels instead of the window, it is only resized
+    /// when the size changes
+    pub(crate) fn set_target_size(&mut self, size: Option<(f32, f32)>) {
+        if self.target_size == size {
+            return;
+        }
+        self.target_size = size;
+        // the next update applies the size of the window again
+        self.window = None;
+        if let Some((w, h)) = size {
+            if self.screen_size != size {
+                self.resize_event(w, h);
+            }
+        }
+    }
+
+    /// The size of the window in pixels, from the last resize
+    pub(crate) fn screen_size(&self) -> Option<(f32, f32)> {
+        self.screen_size
+    }
+
+    /// How many pixels of the window there are in each point of the ui, without the zoom
+    fn native_pixels_per_point(&self) -> f32 {
+        match self.scaling {
+            Scaling::Window => self.scale_factor,
+            Scaling::Reference { resolution, .. } => {
+                let viewport = self.viewport();
+                let scale = (viewport.w / resolution.0).min(viewport.h / resolution.1);
+                if scale > 0.0 {
+                    scale
+                } else {
+                    self.scale_factor
+                }
+            }
+        }
+    }
+
+    /// How many pixels of the window there are in each point of the ui
+    pub(crate) fn pixels_per_point(&self) -> f32 {
+        self.native_pixels_per_point() * self.zoom_factor
+    }
+
+    /// The rect of the window the ui is fitted in, in pixels
+    fn viewport(&self) -> Rect {
+        let (w, h) = self.screen_size.unwrap_or_default();
+        match self.scaling {
+            Scaling::Reference {
+                viewport: Some(viewport),
+                ..
+            } => viewport,
+            _ => Rect::new(0.0, 0.0, w, h),
+        }
+    }
+
+    /// Where the top left corner of the ui is in the window, in pixels
+    pub(crate) fn offset(&self) -> Vec2 {
+        match self.scaling {
+            Scaling::Window => Vec2::ZERO,
+            Scaling::Reference { resolution, .. } => {
+                let viewport = self.viewport();
+                let size = points_to_pixels(
+                    vec2(resolution.0, resolution.1),
+                    self.native_pixels_per_point(),
+                );
+                Vec2::new(
+                    viewport.x + (viewport.w - size.x) / 2.0,
+                    viewport.y + (viewport.h - size.y) / 2.0,
+                )
+            }
+        }
+    }
+
+    /// Set how the ui is fitted in the window
+    pub fn set_scaling(&mut self, scaling: Scaling) {
+        self.scaling = scaling;
+        self.update_screen_rect();
+    }
+
+    pub fn scaling(&self) -> Scaling {
+        self.scaling
+    }
+
+    /// Sends the size of the ui and its pixels per point to egui
+    fn update_screen_rect(&mut self) {
+        let native_pixels_per_point = self.native_pixels_per_point();
+        self.raw
+            .viewports
+            .entry(egui::ViewportId::ROOT)
+            .or_default()
+            .native_pixels_per_point = Some(native_pixels_per_point);
+
+        let size = match self.scaling {
+            Scaling::Window => self.screen_size.map(|(w, h)| vec2(w, h)),
+            Scaling::Reference { resolution, .. } => Some(points_to_pixels(
+                vec2(resolution.0, resolution.1),
+                native_pixels_per_point,
+            )),
+        };
+        if let Some(size) = size {
+            self.raw.screen_rect = Some(egui::Rect::from_min_size(
+                Default::default(),
+                pixels_to_points(size, self.pixels_per_point()),
+            ));
+        }
+    }
+
+    /// Set the zoom of the ui chosen by the user, on top of the scale_factor of the window,
+    /// it is clamped between 0.2 and 5.0
+    pub fn set_zoom_factor(&mut self, zoom_factor: f32) {
+        self.zoom_factor = zoom_factor.clamp(MIN_ZOOM_FACTOR, MAX_ZOOM_FACTOR);
+        self.update_screen_rect();
+    }
+
+    pub fn zoom_factor(&self) -> f32 {
+        self.zoom_factor
+    }
+
+    /// lets Ctrl+Plus, Ctrl+Minus and Ctrl+0 (Cmd on macOS) zoom in, zoom out and reset the zoom
+    pub fn set_zoom_shortcuts(&mut self, zoom_shortcuts: bool) {
+        self.zoom_shortcuts = zoom_shortcuts;
+    }
+
+    /// Applies the zoom shortcuts, returns if the key was used by one of them
+    fn zoom_shortcut(&mut self, key: Key, modifiers: egui::Modifiers) -> bool {
+        if !self.zoom_shortcuts || !modifiers.command {
+            return false;
+        }
+        let zoom_factor = match key {
+            Key::PlusEquals => self.zoom_factor + 0.1,
+            Key::Minus => self.zoom_factor - 0.1,
+            Key::Num0 => 1.0,
+            _ => return false,
+        };
+        self.set_zoom_factor((zoom_factor * 10.0).round() / 10.0);
+        true
+    }
+
+    /// Set the scale_factor and update the screen_rect, egui tessellates at this scale so the
+    /// text stays sharp on high-DPI displays
     pub fn set_scale_factor(&mut self, scale_factor: f32, (w, h): (f32, f32)) {
         self.scale_factor = scale_factor;
         self.resize_event(w, h);
     }
 
-    /// Update screen_rect data with window size
+    /// Turns the copy, cut and paste shortcuts into their [`egui::Event`]
+    fn clipboard_event(&self, key: Key, modifiers: egui::Modifiers) -> Option<egui::Event> {
+        if (modifiers.command && key == Key::C) || (modifiers.ctrl && key == Key::Insert) {
+            Some(egui::Event::Copy)
+        } else if (modifiers.command && key == Key::X) || (modifiers.shift && key == Key::Delete) {
+            Some(egui::Event::Cut)
+        } else if (modifiers.command && key == Key::V) || (modifiers.shift && key == Key::Insert) {
+            // text fields would keep the \r of the line endings of Windows
+            let text = self.clipboard.lock().unwrap().get()?.replace("\r\n", "\n");
+            (!text.is_empty()).then_some(egui::Event::Paste(text))
+        } else {
+            None
+        }
+    }
+
+    /// Set the delay before a held key starts repeating and the interval between repeats,
+    /// `None` disables key repeat
+    pub fn set_key_repeat(&mut self, key_repeat: Option<(Duration, Duration)>) {
+        self.key_repeat = key_repeat;
+    }
+
+    /// Update screen_rect data with window size, in pixels
     pub fn resize_event(&mut self, w: f32, h: f32) {
-        self.raw.screen_rect = Some(egui::Rect::from_min_size(Default::default(), vec2(w, h)));
+        self.screen_size = Some((w, h));
+        self.update_screen_rect();
+    }
+
+    /// lets know if the window gained or lost focus, when it is lost every held key is released
+    pub fn focus_event(&mut self, focused: bool) {
+        self.raw.focused = focused;
+        self.raw.events.push(egui::Event::WindowFocused(focused));
+        if !focused {
+            self.raw.modifiers = Default::default();
+            for (key, _) in self.held_keys.drain() {
+                if let Some(key) = translate_physical_key(key) {
+                    self.raw.events.push(egui::Event::Key {
+                        key,
+                        pressed: false,
+                        repeat: false,
+                        modifiers: Default::default(),
+                    })
+                }
+            }
+        }
+    }
+
+    /// lets know that the cursor left the window, so nothing stays hovered
+    pub fn cursor_left_event(&mut self) {
+        self.raw.events.push(egui::Event::PointerGone);
+    }
+
+    /// Set how much the ui zooms per step of the mouse wheel while holding Ctrl (Cmd on macOS)
+    pub fn set_zoom_sensitivity(&mut self, zoom_sensitivity: f32) {
+        self.zoom_sensitivity = zoom_sensitivity;
     }
 
-    /// lets you know the rotation of the mouse wheel
+    /// lets you know the rotation of the mouse wheel, while holding Ctrl (Cmd on macOS) it zooms
+    /// instead of scrolling
     pub fn mouse_wheel_event(&mut self, x: f32, y: f32) {
-        self.raw.events.push(egui::Event::Scroll(vec2(x, y)));
+        // the wheel only reaches the ui while the pointer is over it
+        if !self.pointer_inside {
+            return;
+        }
+        if self.raw.modifiers.command {
+            let zoom = (y * self.zoom_sensitivity).exp();
+            self.raw.events.push(egui::Event::Zoom(zoom));
+        } else {
+            self.raw.events.push(egui::Event::Scroll(vec2(x, y)));
+        }
     }
 
     /// lets know what character is pressed on the keyboard
@@ -109,6 +459,128 @@ impl Input {
             self.raw.events.push(egui::Event::Text(ch.to_string()));
         }
     }
+
+    /// lets know where a finger touches the screen, only the fingers that start inside of the
+    /// pointer bounds are tracked
+    ///
+    /// The first finger to touch the screen also moves the pointer and presses its primary
+    /// button, so taps work like clicks
+    pub fn touch_event(&mut self, touch: &Touch) {
+        // a finger that can't be mapped stays where it was last seen, so it can still be lifted
+        let Some(pos) = self
+            .screen_to_ui(touch.location.x as f32, touch.location.y as f32)
+            .or_else(|| self.touches.get(&touch.id).copied())
+        else {
+            return;
+        };
+        let phase = match touch.phase {
+            winit::event::TouchPhase::Started => egui::TouchPhase::Start,
+            winit::event::TouchPhase::Moved => egui::TouchPhase::Move,
+            winit::event::TouchPhase::Ended => egui::TouchPhase::End,
+            winit::event::TouchPhase::Cancelled => egui::TouchPhase::Cancel,
+        };
+        // fingers that touch the screen outside of the bounds are ignored until they are lifted
+        let tracked = match phase {
+            egui::TouchPhase::Start => self
+                .pointer_bounds
+                .is_none_or(|bounds| bounds.contains(pos)),
+            _ => self.touches.contains_key(&touch.id),
+        };
+        if !tracked {
+            return;
+        }
+        self.raw.events.push(egui::Event::Touch {
+            device_id: egui::TouchDeviceId(egui::epaint::util::hash(touch.device_id)),
+            id: egui::TouchId(touch.id),
+            phase,
+            pos,
+            force: touch.force.map(|force| force.normalized() as f32),
+        });
+
+        // two fingers moving apart or together zoom
+        match phase {
+            egui::TouchPhase::Start | egui::TouchPhase::Move => {
+                self.touches.insert(touch.id, pos);
+            }
+            egui::TouchPhase::End | egui::TouchPhase::Cancel => {
+                self.touches.remove(&touch.id);
+            }
+        }
+        let pinch_distance = match self.touches.values().collect::<Vec<_>>()[..] {
+            [a, b] => Some(a.distance(*b)),
+            _ => None,
+        };
+        if let (Some(prev), Some(distance)) = (self.pinch_distance, pinch_distance) {
+            if phase == egui::TouchPhase::Move && prev > 0.0 {
+                self.raw.events.push(egui::Event::Zoom(distance / prev));
+            }
+        }
+        self.pinch_distance = pinch_distance;
+
+        let modifiers = self.raw.modifiers;
+        match phase {
+            egui::TouchPhase::Start if self.pointer_touch_id.is_none() => {
+                self.pointer_touch_id = Some(touch.id);
+                self.pointer_pos = pos;
+                self.raw.events.push(egui::Event::PointerMoved(pos));
+                self.raw.events.push(egui::Event::PointerButton {
+                    pos,
+                    button: PointerButton::Primary,
+                    pressed: true,
+                    modifiers,
+                });
+            }
+            egui::TouchPhase::Move if self.pointer_touch_id == Some(touch.id) => {
+                self.pointer_pos = pos;
+                self.raw.events.push(egui::Event::PointerMoved(pos));
+            }
+            egui::TouchPhase::End if self.pointer_touch_id == Some(touch.id) => {
+                self.pointer_touch_id = None;
+                self.pointer_pos = pos;
+                self.raw.events.push(egui::Event::PointerButton {
+                    pos,
+                    button: PointerButton::Primary,
+                    pressed: false,
+                    modifiers,
+                });
+                self.raw.events.push(egui::Event::PointerGone);
+            }
+            egui::TouchPhase::Cancel if self.pointer_touch_id == Some(touch.id) => {
+                self.pointer_touch_id = None;
+                self.raw.events.push(egui::Event::PointerGone);
+            }
+            _ => {}
+        }
+    }
+
+    /// lets know the state of the input method editor, used to compose text in languages like
+    /// Japanese or Chinese. [`Gui::update`](crate::Gui::update) allows it in the window while a
+    /// text field is being edited, through [`Gui::update_platform`](crate::Gui::update_platform)
+    pub fn ime_event(&mut self, ime: Ime) {
+        match ime {
+            Ime::Enabled => {}
+            Ime::Preedit(text, Some(_)) => {
+                if !self.ime_composing {
+                    self.ime_composing = true;
+                    self.raw.events.push(egui::Event::CompositionStart);
+                }
+                self.raw.events.push(egui::Event::CompositionUpdate(text));
+            }
+            Ime::Preedit(_, None) => {}
+            Ime::Commit(text) => {
+                self.ime_composing = false;
+                self.raw.events.push(egui::Event::CompositionEnd(text));
+            }
+            Ime::Disabled => {
+                if self.ime_composing {
+                    self.ime_composing = false;
+                    self.raw
+                        .events
+                        .push(egui::Event::CompositionEnd(String::new()));
+                }
+            }
+        }
+    }
 }
 
 #[inline]
@@ -129,22 +601,94 @@ fn translate_physical_key(key: PhysicalKey) -> Option<egui::Key> {
         KeyCode::ArrowRight => Key::ArrowRight,
         KeyCode::ArrowDown => Key::ArrowDown,
         KeyCode::Backspace => Key::Backspace,
-        KeyCode::Enter => Key::Enter,
+        KeyCode::Enter | KeyCode::NumpadEnter => Key::Enter,
         KeyCode::Tab => Key::Tab,
         KeyCode::Space => Key::Space,
 
+        KeyCode::Minus | KeyCode::NumpadSubtract => Key::Minus,
+        KeyCode::Equal | KeyCode::NumpadAdd => Key::PlusEquals,
+
+        KeyCode::Digit0 | KeyCode::Numpad0 => Key::Num0,
+        KeyCode::Digit1 | KeyCode::Numpad1 => Key::Num1,
+        KeyCode::Digit2 | KeyCode::Numpad2 => Key::Num2,
+        KeyCode::Digit3 | KeyCode::Numpad3 => Key::Num3,
+        KeyCode::Digit4 | KeyCode::Numpad4 => Key::Num4,
+        KeyCode::Digit5 | KeyCode::Numpad5 => Key::Num5,
+        KeyCode::Digit6 | KeyCode::Numpad6 => Key::Num6,
+        KeyCode::Digit7 | KeyCode::Numpad7 => Key::Num7,
+        KeyCode::Digit8 | KeyCode::Numpad8 => Key::Num8,
+        KeyCode::Digit9 | KeyCode::Numpad9 => Key::Num9,
+
         KeyCode::KeyA => Key::A,
+        KeyCode::KeyB => Key::B,
+        KeyCode::KeyC => Key::C,
+        KeyCode::KeyD => Key::D,
+        KeyCode::KeyE => Key::E,
+        KeyCode::KeyF => Key::F,
+        KeyCode::KeyG => Key::G,
+        KeyCode::KeyH => Key::H,
+        KeyCode::KeyI => Key::I,
+        KeyCode::KeyJ => Key::J,
         KeyCode::KeyK => Key::K,
+        KeyCode::KeyL => Key::L,
+        KeyCode::KeyM => Key::M,
+        KeyCode::KeyN => Key::N,
+        KeyCode::KeyO => Key::O,
+        KeyCode::KeyP => Key::P,
+        KeyCode::KeyQ => Key::Q,
+        KeyCode::KeyR => Key::R,
+        KeyCode::KeyS => Key::S,
+        KeyCode::KeyT => Key::T,
         KeyCode::KeyU => Key::U,
+        KeyCode::KeyV => Key::V,
         KeyCode::KeyW => Key::W,
+        KeyCode::KeyX => Key::X,
+        KeyCode::KeyY => Key::Y,
         KeyCode::KeyZ => Key::Z,
 
+        KeyCode::F1 => Key::F1,
+        KeyCode::F2 => Key::F2,
+        KeyCode::F3 => Key::F3,
+        KeyCode::F4 => Key::F4,
+        KeyCode::F5 => Key::F5,
+        KeyCode::F6 => Key::F6,
+        KeyCode::F7 => Key::F7,
+        KeyCode::F8 => Key::F8,
+        KeyCode::F9 => Key::F9,
+        KeyCode::F10 => Key::F10,
+        KeyCode::F11 => Key::F11,
+        KeyCode::F12 => Key::F12,
+        KeyCode::F13 => Key::F13,
+        KeyCode::F14 => Key::F14,
+        KeyCode::F15 => Key::F15,
+        KeyCode::F16 => Key::F16,
+        KeyCode::F17 => Key::F17,
+        KeyCode::F18 => Key::F18,
+        KeyCode::F19 => Key::F19,
+        KeyCode::F20 => Key::F20,
+
         _ => {
             return None;
         }
     })
 }
 
+/// egui only knows five pointer buttons, so the extra buttons some mice have, reported as
+/// [`MouseButton::Other`], are ignored instead of being mistaken for one of those five
+#[inline]
+fn translate_mouse_button(button: MouseButton) -> Option<PointerButton> {
+    Some(match button {
+        MouseButton::Left => PointerButton::Primary,
+        MouseButton::Right => PointerButton::Secondary,
+        MouseButton::Middle => PointerButton::Middle,
+        MouseButton::Back => PointerButton::Extra1,
+        MouseButton::Forward => PointerButton::Extra2,
+        MouseButton::Other(_) => {
+            return None;
+        }
+    })
+}
+
 #[inline]
 fn translate_modifier(keymods: ModifiersState) -> egui::Modifiers {
     egui::Modifiers {
@@ -170,3 +714,372 @@ fn is_printable(chr: char) -> bool {
 
     !is_in_private_use_area && !chr.is_ascii_control()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use egui::{pos2, Event};
+    use winit::event::TouchPhase;
+
+    /// Every key code that has an egui key, with the key it translates to
+    const KEYS: &[(KeyCode, Key)] = &[
+        (KeyCode::Escape, Key::Escape),
+        (KeyCode::Insert, Key::Insert),
+        (KeyCode::Home, Key::Home),
+        (KeyCode::Delete, Key::Delete),
+        (KeyCode::End, Key::End),
+        (KeyCode::PageDown, Key::PageDown),
+        (KeyCode::PageUp, Key::PageUp),
+        (KeyCode::ArrowLeft, Key::ArrowLeft),
+        (KeyCode::ArrowUp, Key::ArrowUp),
+        (KeyCode::ArrowRight, Key::ArrowRight),
+        (KeyCode::ArrowDown, Key::ArrowDown),
+        (KeyCode::Backspace, Key::Backspace),
+        (KeyCode::Enter, Key::Enter),
+        (KeyCode::NumpadEnter, Key::Enter),
+        (KeyCode::Tab, Key::Tab),
+        (KeyCode::Space, Key::Space),
+        (KeyCode::Minus, Key::Minus),
+        (KeyCode::NumpadSubtract, Key::Minus),
+        (KeyCode::Equal, Key::PlusEquals),
+        (KeyCode::NumpadAdd, Key::PlusEquals),
+        (KeyCode::Digit0, Key::Num0),
+        (KeyCode::Digit1, Key::Num1),
+        (KeyCode::Digit2, Key::Num2),
+        (KeyCode::Digit3, Key::Num3),
+        (KeyCode::Digit4, Key::Num4),
+        (KeyCode::Digit5, Key::Num5),
+        (KeyCode::Digit6, Key::Num6),
+        (KeyCode::Digit7, Key::Num7),
+        (KeyCode::Digit8, Key::Num8),
+        (KeyCode::Digit9, Key::Num9),
+        (KeyCode::Numpad0, Key::Num0),
+        (KeyCode::Numpad1, Key::Num1),
+        (KeyCode::Numpad2, Key::Num2),
+        (KeyCode::Numpad3, Key::Num3),
+        (KeyCode::Numpad4, Key::Num4),
+        (KeyCode::Numpad5, Key::Num5),
+        (KeyCode::Numpad6, Key::Num6),
+        (KeyCode::Numpad7, Key::Num7),
+        (KeyCode::Numpad8, Key::Num8),
+        (KeyCode::Numpad9, Key::Num9),
+        (KeyCode::KeyA, Key::A),
+        (KeyCode::KeyB, Key::B),
+        (KeyCode::KeyC, Key::C),
+        (KeyCode::KeyD, Key::D),
+        (KeyCode::KeyE, Key::E),
+        (KeyCode::KeyF, Key::F),
+        (KeyCode::KeyG, Key::G),
+        (KeyCode::KeyH, Key::H),
+        (KeyCode::KeyI, Key::I),
+        (KeyCode::KeyJ, Key::J),
+        (KeyCode::KeyK, Key::K),
+        (KeyCode::KeyL, Key::L),
+        (KeyCode::KeyM, Key::M),
+        (KeyCode::KeyN, Key::N),
+        (KeyCode::KeyO, Key::O),
+        (KeyCode::KeyP, Key::P),
+        (KeyCode::KeyQ, Key::Q),
+        (KeyCode::KeyR, Key::R),
+        (KeyCode::KeyS, Key::S),
+        (KeyCode::KeyT, Key::T),
+        (KeyCode::KeyU, Key::U),
+        (KeyCode::KeyV, Key::V),
+        (KeyCode::KeyW, Key::W),
+        (KeyCode::KeyX, Key::X),
+        (KeyCode::KeyY, Key::Y),
+        (KeyCode::KeyZ, Key::Z),
+        (KeyCode::F1, Key::F1),
+        (KeyCode::F2, Key::F2),
+        (KeyCode::F3, Key::F3),
+        (KeyCode::F4, Key::F4),
+        (KeyCode::F5, Key::F5),
+        (KeyCode::F6, Key::F6),
+        (KeyCode::F7, Key::F7),
+        (KeyCode::F8, Key::F8),
+        (KeyCode::F9, Key::F9),
+        (KeyCode::F10, Key::F10),
+        (KeyCode::F11, Key::F11),
+        (KeyCode::F12, Key::F12),
+        (KeyCode::F13, Key::F13),
+        (KeyCode::F14, Key::F14),
+        (KeyCode::F15, Key::F15),
+        (KeyCode::F16, Key::F16),
+        (KeyCode::F17, Key::F17),
+        (KeyCode::F18, Key::F18),
+        (KeyCode::F19, Key::F19),
+        (KeyCode::F20, Key::F20),
+    ];
+
+    /// Lists every egui key, the match stops compiling when egui adds a key so the list can't
+    /// fall behind
+    macro_rules! all_keys {
+        ($($key:ident),* $(,)?) => {{
+            fn exhaustive(key: Key) {
+                match key {
+                    $(Key::$key)|* => {}
+                }
+            }
+            let _ = exhaustive;
+            [$(Key::$key),*]
+        }};
+    }
+
+    #[test]
+    fn translates_the_table() {
+        for (code, key) in KEYS {
+            assert_eq!(
+                translate_physical_key(PhysicalKey::Code(*code)),
+                Some(*key),
+                "{code:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn every_egui_key_is_reached() {
+        let keys = all_keys![
+            ArrowDown, ArrowLeft, ArrowRight, ArrowUp, Escape, Tab, Backspace, Enter, Space,
+            Insert, Delete, Home, End, PageUp, PageDown, Minus, PlusEquals, Num0, Num1, Num2, Num3,
+            Num4, Num5, Num6, Num7, Num8, Num9, A, B, C, D, E, F, G, H, I, J, K, L, M, N, O, P, Q,
+            R, S, T, U, V, W, X, Y, Z, F1, F2, F3, F4, F5, F6, F7, F8, F9, F10, F11, F12, F13, F14,
+            F15, F16, F17, F18, F19, F20,
+        ];
+        for key in keys {
+            assert!(
+                KEYS.iter().any(|(_, mapped)| *mapped == key),
+                "no key code translates to {key:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn keys_without_egui_key_are_ignored() {
+        for code in [
+            KeyCode::ShiftLeft,
+            KeyCode::ControlRight,
+            KeyCode::CapsLock,
+            KeyCode::NumpadMultiply,
+            KeyCode::F21,
+        ] {
+            assert_eq!(translate_physical_key(PhysicalKey::Code(code)), None);
+        }
+        assert_eq!(
+            translate_physical_key(PhysicalKey::Unidentified(
+                winit::keyboard::NativeKeyCode::Unidentified
+            )),
+            None
+        );
+    }
+
+    #[test]
+    fn translates_mouse_buttons() {
+        for (button, pointer_button) in [
+            (MouseButton::Left, PointerButton::Primary),
+            (MouseButton::Right, PointerButton::Secondary),
+            (MouseButton::Middle, PointerButton::Middle),
+            (MouseButton::Back, PointerButton::Extra1),
+            (MouseButton::Forward, PointerButton::Extra2),
+        ] {
+            assert_eq!(translate_mouse_button(button), Some(pointer_button));
+        }
+    }
+
+    #[test]
+    fn other_mouse_buttons_are_ignored() {
+        for id in [0, 1, 5, 8, u16::MAX] {
+            assert_eq!(translate_mouse_button(MouseButton::Other(id)), None);
+        }
+    }
+
+    fn touch(id: u64, phase: TouchPhase, x: f64, y: f64) -> Touch {
+        Touch {
+            // SAFETY: the id is only hashed by the input, it never reaches winit
+            device_id: unsafe { winit::event::DeviceId::dummy() },
+            phase,
+            location: winit::dpi::PhysicalPosition::new(x, y),
+            force: None,
+            id,
+        }
+    }
+
+    fn button(x: f32, y: f32, pressed: bool) -> Event {
+        Event::PointerButton {
+            pos: pos2(x, y),
+            button: PointerButton::Primary,
+            pressed,
+            modifiers: Default::default(),
+        }
+    }
+
+    /// The events sent to egui, without the raw touches
+    fn pointer_events(input: &mut Input) -> Vec<Event> {
+        input
+            .take()
+            .events
+            .into_iter()
+            .filter(|event| !matches!(event, Event::Touch { .. }))
+            .collect()
+    }
+
+    #[test]
+    fn tap_clicks() {
+        let mut input = Input::default();
+        input.touch_event(&touch(1, TouchPhase::Started, 10.0, 20.0));
+        input.touch_event(&touch(1, TouchPhase::Ended, 10.0, 20.0));
+        assert_eq!(
+            pointer_events(&mut input),
+            [
+                Event::PointerMoved(pos2(10.0, 20.0)),
+                button(10.0, 20.0, true),
+                button(10.0, 20.0, false),
+                Event::PointerGone,
+            ]
+        );
+    }
+
+    #[test]
+    fn drag_moves_the_pointer() {
+        let mut input = Input::default();
+        input.touch_event(&touch(1, TouchPhase::Started, 0.0, 0.0));
+        input.touch_event(&touch(1, TouchPhase::Moved, 5.0, 5.0));
+        input.touch_event(&touch(1, TouchPhase::Moved, 10.0, 0.0));
+        input.touch_event(&touch(1, TouchPhase::Ended, 10.0, 0.0));
+        assert_eq!(
+            pointer_events(&mut input),
+            [
+                Event::PointerMoved(pos2(0.0, 0.0)),
+                button(0.0, 0.0, true),
+                Event::PointerMoved(pos2(5.0, 5.0)),
+                Event::PointerMoved(pos2(10.0, 0.0)),
+                button(10.0, 0.0, false),
+                Event::PointerGone,
+            ]
+        );
+    }
+
+    #[test]
+    fn cancel_releases_the_pointer_without_click() {
+        let mut input = Input::default();
+        input.touch_event(&touch(1, TouchPhase::Started, 0.0, 0.0));
+        input.touch_event(&touch(1, TouchPhase::Cancelled, 0.0, 0.0));
+        assert_eq!(
+            pointer_events(&mut input),
+            [
+                Event::PointerMoved(pos2(0.0, 0.0)),
+                button(0.0, 0.0, true),
+                Event::PointerGone,
+            ]
+        );
+    }
+
+    #[test]
+    fn pinch_zooms_and_keeps_the_first_finger_as_pointer() {
+        let mut input = Input::default();
+        input.touch_event(&touch(1, TouchPhase::Started, 0.0, 0.0));
+        input.touch_event(&touch(2, TouchPhase::Started, 10.0, 0.0));
+        input.touch_event(&touch(2, TouchPhase::Moved, 20.0, 0.0));
+        input.touch_event(&touch(2, TouchPhase::Ended, 20.0, 0.0));
+        assert_eq!(
+            pointer_events(&mut input),
+            [
+                Event::PointerMoved(pos2(0.0, 0.0)),
+                button(0.0, 0.0, true),
+                Event::Zoom(2.0),
+            ]
+        );
+
+        input.touch_event(&touch(1, TouchPhase::Ended, 0.0, 0.0));
+        assert_eq!(
+            pointer_events(&mut input),
+            [button(0.0, 0.0, false), Event::PointerGone]
+        );
+    }
+
+    #[test]
+    fn touches_outside_of_the_bounds_are_ignored() {
+        let mut input = Input {
+            pointer_bounds: Some(egui::Rect::from_min_size(
+                pos2(0.0, 0.0),
+                egui::vec2(100.0, 100.0),
+            )),
+            ..Default::default()
+        };
+        input.touch_event(&touch(1, TouchPhase::Started, 150.0, 50.0));
+        input.touch_event(&touch(1, TouchPhase::Moved, 50.0, 50.0));
+        input.touch_event(&touch(1, TouchPhase::Ended, 50.0, 50.0));
+        assert!(input.take().events.is_empty());
+
+        input.touch_event(&touch(2, TouchPhase::Started, 50.0, 50.0));
+        input.touch_event(&touch(2, TouchPhase::Moved, 150.0, 50.0));
+        assert_eq!(
+            pointer_events(&mut input),
+            [
+                Event::PointerMoved(pos2(50.0, 50.0)),
+                button(50.0, 50.0, true),
+                Event::PointerMoved(pos2(150.0, 50.0)),
+            ]
+        );
+    }
+
+    #[test]
+    fn every_touch_is_forwarded() {
+        let mut input = Input::default();
+        input.touch_event(&touch(1, TouchPhase::Started, 0.0, 0.0));
+        input.touch_event(&touch(2, TouchPhase::Started, 10.0, 0.0));
+        let touches = input
+            .take()
+            .events
+            .into_iter()
+            .filter_map(|event| match event {
+                Event::Touch { id, phase, pos, .. } => Some((id, phase, pos)),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            touches,
+            [
+                (egui::TouchId(1), egui::TouchPhase::Start, pos2(0.0, 0.0)),
+                (egui::TouchId(2), egui::TouchPhase::Start, pos2(10.0, 0.0)),
+            ]
+        );
+    }
+
+    #[test]
+    fn singular_transform_keeps_fingers_where_they_were() {
+        let mut input = Input::default();
+        input.touch_event(&touch(1, TouchPhase::Started, 10.0, 20.0));
+        input.transform = Mat4::from_scale(vec3(0.0, 1.0, 1.0));
+        input.touch_event(&touch(2, TouchPhase::Started, 30.0, 30.0));
+        input.touch_event(&touch(1, TouchPhase::Ended, 50.0, 50.0));
+        assert_eq!(
+            pointer_events(&mut input),
+            [
+                Event::PointerMoved(pos2(10.0, 20.0)),
+                button(10.0, 20.0, true),
+                button(10.0, 20.0, false),
+                Event::PointerGone,
+            ]
+        );
+    }
+
+    fn paste(text: &str) -> Option<Event> {
+        let input = Input::default();
+        input.clipboard.lock().unwrap().set(text.to_owned());
+        let modifiers = egui::Modifiers {
+            command: true,
+            ..Default::default()
+        };
+        input.clipboard_event(Key::V, modifiers)
+    }
+
+    #[test]
+    fn paste_uses_unix_line_endings() {
+        assert_eq!(paste("a\r\nb\n"), Some(Event::Paste("a\nb\n".to_owned())));
+    }
+
+    #[test]
+    fn empty_paste_is_ignored() {
+        assert_eq!(paste(""), None);
+    }
+}
@@ -1,17 +1,36 @@
+mod clipboard;
+mod cursor;
 mod input;
+mod open_url;
 mod painter;
+mod units;
+mod world;
 
+#[cfg(feature = "clipboard")]
+pub use clipboard::SystemClipboard;
+pub use clipboard::{Clipboard, MemoryClipboard};
+use cursor::translate_cursor_icon;
 pub use egui;
 use ggez::{
     context::Has,
+    glam::Mat4,
     graphics::{self, Canvas, DrawParam, Drawable, GraphicsContext},
+    GameResult,
 };
-pub use input::Input;
-use painter::Painter;
+pub use input::{Input, Scaling};
+#[cfg(feature = "open_url")]
+pub use open_url::SystemBrowser;
+pub use open_url::{OpenUrlHandler, RecordOpenUrl};
+pub use painter::CallbackFn;
+use painter::{transform_rect, Painter};
 use std::{
     ops::Deref,
     sync::{Arc, Mutex},
+    time::{Duration, Instant},
 };
+use units::rect_to_pixels;
+use winit::dpi::{PhysicalPosition, PhysicalSize};
+pub use world::WorldGui;
 
 /// Contains the [`egui::Context`] and the [`Painter`].
 ///
@@ -19,6 +38,9 @@ use std::{
 pub struct GuiContext {
     context: egui::Context,
     painter: Arc<Mutex<Painter>>,
+    clipboard: Arc<Mutex<dyn Clipboard>>,
+    output: Arc<Mutex<egui::FullOutput>>,
+    repaint: bool,
 }
 
 impl Deref for GuiContext {
@@ -30,16 +52,27 @@ impl Deref for GuiContext {
 
 impl Drop for GuiContext {
     fn drop(&mut self) {
-        let egui::FullOutput {
-            textures_delta,
-            shapes,
-            pixels_per_point,
-            ..
-        } = self.context.end_frame();
+        let mut full_output = self.context.end_frame();
+
+        let copied_text = &full_output.platform_output.copied_text;
+        if !copied_text.is_empty() {
+            self.clipboard.lock().unwrap().set(copied_text.clone());
+        }
 
         let mut painter = self.painter.lock().unwrap();
-        painter.shapes = self.context.tessellate(shapes, pixels_per_point);
-        painter.textures_delta.push_front(textures_delta);
+        if self.repaint {
+            painter.shapes = self.context.tessellate(
+                std::mem::take(&mut full_output.shapes),
+                full_output.pixels_per_point,
+            );
+            painter.pixels_per_point = full_output.pixels_per_point;
+            painter.dirty = true;
+        }
+        painter
+            .textures_delta
+            .push_back(std::mem::take(&mut full_output.textures_delta));
+
+        *self.output.lock().unwrap() = full_output;
     }
 }
 
@@ -82,19 +115,43 @@ impl Drop for GuiContext {
 ///     }
 /// }
 /// ```
-#[derive(Default)]
 pub struct Gui {
     context: egui::Context,
     pub input: Input,
     painter: Arc<Mutex<Painter>>,
+    output: Arc<Mutex<egui::FullOutput>>,
+    open_url_handler: Box<dyn OpenUrlHandler>,
+    cursor_icon: Option<egui::CursorIcon>,
+    ime_allowed: bool,
+    reactive: bool,
+    last_repaint: Instant,
+}
+
+impl Default for Gui {
+    fn default() -> Self {
+        // the zoom shortcuts are handled by the input, so the pointer is mapped with the zoom
+        let context = egui::Context::default();
+        context.options_mut(|options| options.zoom_with_keyboard = false);
+        Self {
+            context,
+            input: Default::default(),
+            painter: Default::default(),
+            output: Default::default(),
+            open_url_handler: Box::new(RecordOpenUrl::default()),
+            cursor_icon: None,
+            ime_allowed: false,
+            reactive: false,
+            last_repaint: Instant::now(),
+        }
+    }
 }
 
 impl Gui {
-    /// Create a [`Gui`] with extra information for use the [`Input::set_scale_factor`]
+    /// Create a [`Gui`] with the size and the scale factor of the window, both are kept up to
+    /// date by [`Input::update`]
     pub fn new(ctx: &ggez::Context) -> Self {
         let mut input = Input::default();
-        let (w, h) = ctx.gfx.size();
-        input.set_scale_factor(1.0, (w, h));
+        input.update_window(ctx);
         Self {
             input,
             ..Default::default()
@@ -104,46 +161,272 @@ impl Gui {
     pub fn update(&mut self, ctx: &mut ggez::Context) {
         self.update_input(ctx);
         self.update_painter(ctx);
+        self.update_platform(ctx);
     }
 
     pub fn update_input(&mut self, ctx: &ggez::Context) {
+        self.input.transform = self.painter.lock().unwrap().transform;
         self.input.update(ctx);
     }
 
     pub fn update_painter(&mut self, ctx: &mut ggez::Context) {
-        self.painter
+        self.painter.lock().unwrap().update(ctx);
+    }
+
+    /// Apply the non-rendering output of the last frame to the window, such as the cursor icon
+    /// or the URL to open
+    pub fn update_platform(&mut self, ctx: &ggez::Context) {
+        let window = ctx.gfx.window();
+
+        let cursor_icon = self.output.lock().unwrap().platform_output.cursor_icon;
+        if self.cursor_icon.replace(cursor_icon) != Some(cursor_icon) {
+            match translate_cursor_icon(cursor_icon) {
+                Some(icon) => {
+                    window.set_cursor(icon);
+                    window.set_cursor_visible(true);
+                }
+                None => window.set_cursor_visible(false),
+            }
+        }
+
+        let open_url = self.output.lock().unwrap().platform_output.open_url.take();
+        if let Some(open_url) = open_url {
+            self.open_url_handler.open_url(&open_url);
+        }
+
+        let ime_cursor_rect = self.ime_cursor_rect();
+        if self.ime_allowed != ime_cursor_rect.is_some() {
+            self.ime_allowed = ime_cursor_rect.is_some();
+            window.set_ime_allowed(self.ime_allowed);
+        }
+        if let Some(rect) = ime_cursor_rect {
+            window.set_ime_cursor_area(
+                PhysicalPosition::new(rect.x, rect.y),
+                PhysicalSize::new(rect.w, rect.h),
+            );
+        }
+    }
+
+    /// Where the text cursor is, in pixels, while a text field is being edited so the host can
+    /// place the candidate window of the input method editor next to it
+    ///
+    /// egui only reports the position of the cursor, so the rect is one point wide and tall
+    pub fn ime_cursor_rect(&self) -> Option<graphics::Rect> {
+        let pos = self
+            .output
+            .lock()
+            .unwrap()
+            .platform_output
+            .text_cursor_pos?;
+        let rect = rect_to_pixels(
+            egui::Rect::from_min_size(pos, egui::vec2(1.0, 1.0)),
+            self.context.pixels_per_point(),
+        );
+        // the same transform the pointer is mapped with, it already places the ui where the
+        // Scaling puts it
+        Some(transform_rect(self.input.transform, rect))
+    }
+
+    /// The non-rendering output of the last frame, the URL to open is taken out of it by
+    /// [`Gui::update_platform`]
+    pub fn last_platform_output(&self) -> egui::PlatformOutput {
+        self.output.lock().unwrap().platform_output.clone()
+    }
+
+    /// The events of the widgets in the last frame, such as clicks or text changes
+    pub fn events(&self) -> Vec<egui::output::OutputEvent> {
+        self.output.lock().unwrap().platform_output.events.clone()
+    }
+
+    /// How long egui can wait before it needs to run again, [`Duration::ZERO`] means as soon as
+    /// possible
+    pub fn repaint_delay(&self) -> Duration {
+        self.output
             .lock()
             .unwrap()
-            .update(ctx, self.input.scale_factor);
+            .viewport_output
+            .get(&egui::ViewportId::ROOT)
+            .map_or(Duration::ZERO, |viewport| viewport.repaint_delay)
+    }
+
+    /// Whether egui has something new to show, because of new input or because it asked to
+    /// repaint, the host loop can skip frames while this is `false`
+    ///
+    /// The requests of egui are read from the [`Gui::repaint_delay`] of the last frame,
+    /// [`egui::Context::has_requested_repaint`] is not used because it is also `true` for a
+    /// repaint requested after a delay that has not passed yet
+    pub fn needs_repaint(&self) -> bool {
+        !self.input.raw.events.is_empty()
+            || self.input.raw.screen_rect.is_some()
+            || self.last_repaint.elapsed() >= self.repaint_delay()
+    }
+
+    /// Set the zoom of the ui, it multiplies the scale factor of the window so the text stays
+    /// sharp, use this instead of [`egui::Context::set_zoom_factor`] so the pointer follows it
+    pub fn set_zoom_factor(&mut self, zoom_factor: f32) {
+        self.input.set_zoom_factor(zoom_factor);
+    }
+
+    pub fn zoom_factor(&self) -> f32 {
+        self.input.zoom_factor()
+    }
+
+    /// Set how the ui is fitted in the window, see [`Scaling`]
+    pub fn set_scaling(&mut self, scaling: Scaling) {
+        self.input.set_scaling(scaling);
+    }
+
+    /// Converts a rect in points to the pixels of the window, where the ui is placed
+    fn points_to_window(&self, rect: egui::Rect, pixels_per_point: f32) -> graphics::Rect {
+        let mut rect = rect_to_pixels(rect, pixels_per_point);
+        rect.translate(self.input.offset());
+        rect
+    }
+
+    /// Moves the ui to where the [`Scaling`] places it, before the transform of the param
+    fn place(&self, param: DrawParam) -> DrawParam {
+        let transform = Mat4::from(param.transform.to_bare_matrix())
+            * Mat4::from_translation(self.input.offset().extend(0.0));
+        param.transform(transform)
+    }
+
+    /// In reactive mode the frames that don't [need a repaint](Gui::needs_repaint) are not
+    /// tessellated and the painter keeps drawing the meshes of the last repaint
+    pub fn set_reactive(&mut self, reactive: bool) {
+        self.reactive = reactive;
+    }
+
+    /// The viewports of the last frame, with the commands egui sent to them
+    pub fn viewport_output(&self) -> egui::ViewportIdMap<egui::ViewportOutput> {
+        self.output.lock().unwrap().viewport_output.clone()
+    }
+
+    /// The rects of every visible egui area, such as windows and panels, in the pixels of the
+    /// screen where the gui was last drawn, from the bottom layer to the top one
+    pub fn ui_rects(&self) -> Vec<graphics::Rect> {
+        // read before the closure, the context is already locked inside of it
+        let pixels_per_point = self.context.pixels_per_point();
+        self.context.memory(|memory| {
+            let visible = memory.areas().visible_layer_ids();
+            memory
+                .layer_ids()
+                .filter(|layer_id| visible.contains(layer_id))
+                .filter_map(|layer_id| memory.area_rect(layer_id.id))
+                .map(|rect| {
+                    transform_rect(self.input.transform, rect_to_pixels(rect, pixels_per_point))
+                })
+                .collect()
+        })
     }
 
     pub fn begin_frame(&mut self) {
         self.context.begin_frame(self.input.take());
     }
 
+    /// Drops the meshes of the painter, they are built again in the next
+    /// [`Gui::update_painter`] from the last tessellated frame, so in reactive mode the ui
+    /// stays visible even when nothing was repainted
     pub fn clear_painter(&mut self) {
         self.painter.lock().unwrap().clear()
     }
 
+    /// Makes an [`graphics::Image`] usable in egui, e.g. with [`egui::Image`], without copying
+    /// its pixels
+    pub fn register_image(&mut self, image: graphics::Image) -> egui::TextureId {
+        self.painter.lock().unwrap().register_image(image)
+    }
+
+    /// Replaces the [`graphics::Image`] behind a [`egui::TextureId`] given by
+    /// [`Gui::register_image`]
+    pub fn update_image(&mut self, id: egui::TextureId, image: graphics::Image) {
+        self.painter.lock().unwrap().update_image(id, image)
+    }
+
+    /// Forgets a [`graphics::Image`] registered with [`Gui::register_image`]
+    pub fn unregister_image(&mut self, id: egui::TextureId) {
+        self.painter.lock().unwrap().unregister_image(id)
+    }
+
+    /// Replace the [`Clipboard`] used for copy, cut and paste
+    pub fn set_clipboard(&mut self, clipboard: impl Clipboard + 'static) {
+        self.input.clipboard = Arc::new(Mutex::new(clipboard));
+    }
+
+    /// Replace the [`OpenUrlHandler`] called when egui asks to open a URL
+    pub fn set_open_url_handler(&mut self, open_url_handler: impl OpenUrlHandler + 'static) {
+        self.open_url_handler = Box::new(open_url_handler);
+    }
+
+    /// Lays out the gui for a target of `size` pixels instead of the window, such as the image
+    /// of [`Gui::draw_to_image`], `None` goes back to the size of the window
+    pub fn set_target_size(&mut self, size: Option<(u32, u32)>) {
+        self.input
+            .set_target_size(size.map(|(w, h)| (w as f32, h as f32)));
+    }
+
+    /// Draws the gui into `image` instead of the frame, the image is cleared to transparent first
+    ///
+    /// The gui is laid out for the size of the image from the next frame on, until
+    /// [`Gui::set_target_size`] is called with `None`, and the offset of the [`Scaling`] in the
+    /// window is not applied. The colors of the image are premultiplied by their alpha, so draw
+    /// it with [`graphics::BlendMode::PREMULTIPLIED`]. The image must be created with
+    /// [`graphics::Image::new_canvas_image`]
+    pub fn draw_to_image(
+        &mut self,
+        ctx: &mut ggez::Context,
+        image: &graphics::Image,
+    ) -> GameResult<graphics::Image> {
+        let (w, h) = (image.width(), image.height());
+        self.set_target_size(Some((w, h)));
+        let mut canvas =
+            Canvas::from_image(ctx, image.clone(), graphics::Color::new(0.0, 0.0, 0.0, 0.0));
+        self.painter.lock().unwrap().draw(
+            &mut canvas,
+            DrawParam::default(),
+            Some((w as f32, h as f32)),
+        );
+        canvas.finish(ctx)?;
+        Ok(image.clone())
+    }
+
     /// Return an [`EguiContext`] for update the gui
     pub fn ctx(&mut self) -> GuiContext {
+        self.context.set_zoom_factor(self.input.zoom_factor);
+        let repaint = !self.reactive || self.needs_repaint();
+        // egui asks for the delay again every frame, so it is counted from the last repaint
+        if repaint {
+            self.last_repaint = Instant::now();
+        }
         self.context.begin_frame(self.input.take());
         GuiContext {
             context: self.context.clone(),
             painter: self.painter.clone(),
+            clipboard: self.input.clipboard.clone(),
+            output: self.output.clone(),
+            repaint,
         }
     }
 }
 
 impl Drawable for Gui {
-    fn draw(&self, canvas: &mut Canvas, _param: impl Into<DrawParam>) {
-        self.painter
-            .lock()
-            .unwrap()
-            .draw(canvas, self.input.scale_factor);
+    /// Draws the gui with the transform of the param on top of the pixels of the window
+    ///
+    /// The clip rects of egui become the axis-aligned bounding box of their transformed rect, so
+    /// with a rotated or skewed transform some content can show outside of its clip rect
+    fn draw(&self, canvas: &mut Canvas, param: impl Into<DrawParam>) {
+        self.painter.lock().unwrap().draw(
+            canvas,
+            self.place(param.into()),
+            self.input.screen_size(),
+        );
     }
 
+    /// The bounding rect of everything egui painted, in pixels
     fn dimensions(&self, _gfx: &impl Has<GraphicsContext>) -> graphics::Rect {
-        graphics::Rect::zero() // FIXME
+        let painter = self.painter.lock().unwrap();
+        match painter.bounds {
+            Some(bounds) => self.points_to_window(bounds, painter.pixels_per_point),
+            None => graphics::Rect::zero(),
+        }
     }
 }
@@ -0,0 +1,51 @@
+use std::sync::{Arc, Mutex};
+
+/// What to do when [`egui`] asks to open a URL, e.g. when an [`egui::Hyperlink`] is clicked
+///
+/// The [`Gui`](crate::Gui) uses a [`RecordOpenUrl`] by default, to open the URLs in the browser
+/// enable the `open_url` feature and set a [`SystemBrowser`] with
+/// [`Gui::set_open_url_handler`](crate::Gui::set_open_url_handler)
+pub trait OpenUrlHandler {
+    fn open_url(&mut self, open_url: &egui::OpenUrl);
+}
+
+/// An [`OpenUrlHandler`] that only records the requested URLs
+///
+/// It can be cloned to keep a handle to the same records after giving it to the
+/// [`Gui`](crate::Gui)
+#[derive(Default, Clone, Debug)]
+pub struct RecordOpenUrl {
+    requests: Arc<Mutex<Vec<egui::OpenUrl>>>,
+}
+
+impl RecordOpenUrl {
+    /// Returns the URLs requested so far
+    pub fn requests(&self) -> Vec<egui::OpenUrl> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    /// Returns and forgets the URLs requested so far
+    pub fn take(&self) -> Vec<egui::OpenUrl> {
+        std::mem::take(&mut *self.requests.lock().unwrap())
+    }
+}
+
+impl OpenUrlHandler for RecordOpenUrl {
+    fn open_url(&mut self, open_url: &egui::OpenUrl) {
+        self.requests.lock().unwrap().push(open_url.clone());
+    }
+}
+
+/// An [`OpenUrlHandler`] that opens the URLs in the browser of the system
+#[cfg(feature = "open_url")]
+#[derive(Default, Clone, Copy, Debug)]
+pub struct SystemBrowser;
+
+#[cfg(feature = "open_url")]
+impl OpenUrlHandler for SystemBrowser {
+    fn open_url(&mut self, open_url: &egui::OpenUrl) {
+        if let Err(err) = webbrowser::open(&open_url.url) {
+            eprintln!("Failed to open {}: {err}", open_url.url);
+        }
+    }
+}
@@ -1,7 +1,11 @@
 use std::collections::{HashMap, LinkedList};
+use std::sync::Arc;
 
+use ggez::glam::{vec3, Mat4};
 use ggez::graphics::{self, BlendComponent, BlendFactor, BlendMode, BlendOperation};
 
+use crate::units::{points_to_pixels, rect_to_pixels};
+
 #[derive(Default, Clone)]
 struct PixBuf {
     pix: Vec<u8>,
@@ -9,18 +13,99 @@ struct PixBuf {
     h: usize,
 }
 
+/// Draws with [`ggez`] inside of the layout of [`egui`]
+///
+/// Wrap it in an [`egui::PaintCallback`] and add it to an [`egui::Painter`], it is called with
+/// the [`egui::PaintCallbackInfo`] and the canvas the gui is drawn on, with the scissor rect set
+/// to the clip rect of the callback. The shaders, blend mode, sampler, projection and scissor
+/// rect of the canvas are restored afterwards, the canvas can't report its shader params so a
+/// callback that sets them must restore them itself
+/// ```ignore
+/// let callback = egui::PaintCallback {
+///     rect,
+///     callback: Arc::new(ggegui::CallbackFn::new(|info, canvas| {
+///         let rect = info.viewport_in_pixels();
+///         canvas.draw(&particles, DrawParam::default().dest([rect.left_px as f32, rect.top_px as f32]));
+///     })),
+/// };
+/// ui.painter().add(callback);
+/// ```
+pub struct CallbackFn {
+    f: Box<dyn Fn(&egui::PaintCallbackInfo, &mut graphics::Canvas) + Send + Sync>,
+}
+
+impl CallbackFn {
+    pub fn new(
+        callback: impl Fn(&egui::PaintCallbackInfo, &mut graphics::Canvas) + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            f: Box::new(callback),
+        }
+    }
+}
+
+#[derive(Clone)]
+enum PaintJob {
+    Mesh {
+        texture_id: egui::TextureId,
+        mesh: graphics::Mesh,
+        clip: graphics::Rect,
+    },
+    Callback {
+        callback: Arc<CallbackFn>,
+        rect: egui::Rect,
+        clip_rect: egui::Rect,
+        clip: graphics::Rect,
+        pixels_per_point: f32,
+    },
+}
+
 #[derive(Default, Clone)]
 pub struct Painter {
     pub(crate) shapes: Vec<egui::ClippedPrimitive>,
     pub(crate) textures_delta: LinkedList<egui::TexturesDelta>,
-    paint_jobs: Vec<(egui::TextureId, graphics::Mesh, graphics::Rect)>,
+    pub(crate) dirty: bool,
+    pub(crate) transform: Mat4,
+    pub(crate) bounds: Option<egui::Rect>,
+    pub(crate) pixels_per_point: f32,
+    paint_jobs: Vec<PaintJob>,
     textures: HashMap<egui::TextureId, graphics::Image>,
     images: HashMap<egui::TextureId, PixBuf>,
+    options: HashMap<egui::TextureId, egui::TextureOptions>,
+    next_user_texture: u64,
 }
 
 impl Painter {
-    pub fn draw(&mut self, canvas: &mut graphics::Canvas, scale_factor: f32) {
+    /// Draws the ui in the pixels of a target of `screen_size`, whatever the projection of the
+    /// canvas is, the state of the canvas is restored afterwards. The callbacks are skipped
+    /// while the size is not known
+    pub fn draw(
+        &mut self,
+        canvas: &mut graphics::Canvas,
+        param: graphics::DrawParam,
+        screen_size: Option<(f32, f32)>,
+    ) {
+        // the meshes are already in pixels, so only the transform of the param is applied
+        self.transform = Mat4::from(param.transform.to_bare_matrix());
+        // the vertices and textures of egui are premultiplied, so the tint must be as well or a
+        // translucent color would only lower the alpha and the ui would look washed out
+        let graphics::Color { r, g, b, a } = param.color;
+        let mesh_param = graphics::DrawParam::default()
+            .transform(self.transform)
+            .color(graphics::Color::new(r * a, g * a, b * a, a))
+            .z(param.z);
+
         let prev_blend = canvas.blend_mode();
+        let prev_sampler = canvas.sampler();
+        let prev_scissor = canvas.scissor_rect();
+        let prev_projection = canvas.projection();
+        let prev_screen_coordinates = canvas.screen_coordinates();
+        if let Some((w, h)) = screen_size {
+            canvas.set_screen_coordinates(graphics::Rect::new(0.0, 0.0, w, h));
+        }
+        let ui_projection = Mat4::from(canvas.projection());
+        // the screen coordinates of the canvas are not pixels, so the callbacks wait for a size
+        let screen_size_px = screen_size.map(|(w, h)| [w as u32, h as u32]);
         canvas.set_blend_mode(BlendMode {
             color: BlendComponent {
                 src_factor: BlendFactor::One,
@@ -33,74 +118,181 @@ impl Painter {
                 operation: BlendOperation::Add,
             },
         });
-        for (id, mesh, clip) in self.paint_jobs.iter() {
-            canvas.set_scissor_rect(*clip).unwrap();
-            canvas.draw_textured_mesh(
-                mesh.clone(),
-                self.textures[id].clone(),
-                graphics::DrawParam::default().scale([scale_factor, scale_factor]),
-            );
+        let egui_blend = canvas.blend_mode();
+        for paint_job in self.paint_jobs.iter() {
+            match paint_job {
+                PaintJob::Mesh {
+                    texture_id,
+                    mesh,
+                    clip,
+                } => {
+                    // an image can be unregistered while a mesh still uses it
+                    let Some(texture) = self.textures.get(texture_id) else {
+                        continue;
+                    };
+                    if canvas
+                        .set_scissor_rect(transform_rect(self.transform, *clip))
+                        .is_err()
+                    {
+                        continue;
+                    }
+                    canvas.set_sampler(translate_texture_options(
+                        self.options.get(texture_id).copied().unwrap_or_default(),
+                    ));
+                    canvas.draw_textured_mesh(mesh.clone(), texture.clone(), mesh_param);
+                }
+                PaintJob::Callback {
+                    callback,
+                    rect,
+                    clip_rect,
+                    clip,
+                    pixels_per_point,
+                } => {
+                    let Some(screen_size_px) = screen_size_px else {
+                        continue;
+                    };
+                    let info = egui::PaintCallbackInfo {
+                        viewport: *rect,
+                        clip_rect: *clip_rect,
+                        pixels_per_point: *pixels_per_point,
+                        screen_size_px,
+                    };
+                    let shader = canvas.shader();
+                    let text_shader = canvas.text_shader();
+                    if canvas
+                        .set_scissor_rect(transform_rect(self.transform, *clip))
+                        .is_err()
+                    {
+                        continue;
+                    }
+                    canvas.set_projection(ui_projection * self.transform);
+                    canvas.set_blend_mode(prev_blend);
+                    canvas.set_sampler(prev_sampler);
+
+                    (callback.f)(&info, canvas);
+
+                    canvas.set_shader(&shader);
+                    canvas.set_text_shader(text_shader);
+                    canvas.set_projection(ui_projection);
+                    canvas.set_blend_mode(egui_blend);
+                }
+            }
+        }
+        if canvas.set_scissor_rect(prev_scissor).is_err() {
+            canvas.set_default_scissor_rect();
+        }
+        match prev_screen_coordinates {
+            Some(rect) => canvas.set_screen_coordinates(rect),
+            None => canvas.set_projection(prev_projection),
         }
-        canvas.set_default_scissor_rect();
         canvas.set_blend_mode(prev_blend);
+        canvas.set_sampler(prev_sampler);
     }
 
+    /// Drops the meshes, they are built again from the last shapes in the next update
     pub fn clear(&mut self) {
         self.paint_jobs.clear();
+        self.dirty = true;
     }
 
-    pub fn update(&mut self, ctx: &mut ggez::Context, scale_factor: f32) {
+    pub fn update(&mut self, ctx: &mut ggez::Context) {
         // Create and free textures
         while let Some(textures_delta) = self.textures_delta.pop_front() {
             self.update_textures(ctx, textures_delta);
         }
 
-        // generating meshes
+        // the meshes are only generated again when there are new shapes
+        if !self.dirty {
+            return;
+        }
+        self.dirty = false;
+        self.paint_jobs.clear();
+        self.bounds = None;
+
+        // generating meshes and callbacks
         for egui::ClippedPrimitive {
             primitive,
             clip_rect,
         } in self.shapes.iter()
         {
+            let clip = rect_to_pixels(*clip_rect, self.pixels_per_point);
             match primitive {
                 egui::epaint::Primitive::Mesh(mesh) => {
                     if mesh.vertices.len() < 3 {
                         continue;
                     }
+                    self.add_bounds(mesh.calc_bounds().intersect(*clip_rect));
 
                     let vertices = mesh
                         .vertices
                         .iter()
-                        .map(|v| graphics::Vertex {
-                            position: [v.pos.x, v.pos.y],
-                            uv: [v.uv.x, v.uv.y],
-                            color: egui::Rgba::from(v.color).to_array(),
+                        .map(|v| {
+                            let pos = points_to_pixels(v.pos.to_vec2(), self.pixels_per_point);
+                            graphics::Vertex {
+                                position: [pos.x, pos.y],
+                                uv: [v.uv.x, v.uv.y],
+                                color: egui::Rgba::from(v.color).to_array(),
+                            }
                         })
                         .collect::<Vec<_>>();
 
-                    self.paint_jobs.push((
-                        mesh.texture_id,
-                        graphics::Mesh::from_data(
+                    self.paint_jobs.push(PaintJob::Mesh {
+                        texture_id: mesh.texture_id,
+                        mesh: graphics::Mesh::from_data(
                             ctx,
                             graphics::MeshData {
                                 vertices: vertices.as_slice(),
                                 indices: mesh.indices.as_slice(),
                             },
                         ),
-                        graphics::Rect::new(
-                            clip_rect.min.x * scale_factor,
-                            clip_rect.min.y * scale_factor,
-                            (clip_rect.max.x - clip_rect.min.x) * scale_factor,
-                            (clip_rect.max.y - clip_rect.min.y) * scale_factor,
-                        ),
-                    ));
+                        clip,
+                    });
                 }
-                egui::epaint::Primitive::Callback(_) => {
-                    panic!("Custom rendering callbacks are not implemented yet");
+                egui::epaint::Primitive::Callback(callback) => {
+                    let Ok(callback_fn) = callback.callback.clone().downcast::<CallbackFn>() else {
+                        eprintln!("Got paint callback that is not a ggegui::CallbackFn");
+                        continue;
+                    };
+                    self.add_bounds(callback.rect.intersect(*clip_rect));
+                    self.paint_jobs.push(PaintJob::Callback {
+                        callback: callback_fn,
+                        rect: callback.rect,
+                        clip_rect: *clip_rect,
+                        clip,
+                        pixels_per_point: self.pixels_per_point,
+                    });
                 }
             }
         }
     }
 
+    fn add_bounds(&mut self, rect: egui::Rect) {
+        if rect.is_positive() {
+            self.bounds = Some(self.bounds.map_or(rect, |bounds| bounds.union(rect)));
+        }
+    }
+
+    pub fn register_image(&mut self, image: graphics::Image) -> egui::TextureId {
+        let id = egui::TextureId::User(self.next_user_texture);
+        self.next_user_texture += 1;
+        self.textures.insert(id, image);
+        id
+    }
+
+    pub fn update_image(&mut self, id: egui::TextureId, image: graphics::Image) {
+        if matches!(id, egui::TextureId::User(_)) && self.textures.contains_key(&id) {
+            self.textures.insert(id, image);
+        } else {
+            eprintln!("Got update request for unknown image {id:?}");
+        }
+    }
+
+    pub fn unregister_image(&mut self, id: egui::TextureId) {
+        if matches!(id, egui::TextureId::User(_)) {
+            self.textures.remove(&id);
+        }
+    }
+
     pub fn update_textures(
         &mut self,
         ctx: &mut ggez::Context,
@@ -109,13 +301,15 @@ impl Painter {
         // set textures
         for (id, delta) in &textures_delta.set {
             let pixbuf = PixBuf::from_image_data(&delta.image);
+            self.options.insert(*id, delta.options);
             if let Some(pos) = delta.pos {
-                eprintln!("Error: Non-zero offset texture updates are not implemented yet");
-                let Some(mut img) = self.images.remove(id) else {
+                let Some(img) = self.images.get_mut(id) else {
                     eprintln!("Got update request for unknown image");
                     continue;
                 };
-                img.blit(&pixbuf, (pos[0], pos[1]));
+                if img.blit(&pixbuf, (pos[0], pos[1])) {
+                    self.textures.insert(*id, img.to_texture(ctx));
+                }
             } else {
                 self.textures.insert(*id, pixbuf.to_texture(ctx));
                 self.images.insert(*id, pixbuf);
@@ -126,10 +320,45 @@ impl Painter {
         for id in &textures_delta.free {
             self.textures.remove(id);
             self.images.remove(id);
+            self.options.remove(id);
         }
     }
 }
 
+/// The bounding box of a rect in pixels after `transform`, clamped to the positive quadrant
+pub(crate) fn transform_rect(transform: Mat4, rect: graphics::Rect) -> graphics::Rect {
+    let corners = [
+        (rect.left(), rect.top()),
+        (rect.right(), rect.top()),
+        (rect.left(), rect.bottom()),
+        (rect.right(), rect.bottom()),
+    ]
+    .map(|(x, y)| transform.transform_point3(vec3(x, y, 0.0)));
+    let (min, max) = corners
+        .iter()
+        .fold((corners[0], corners[0]), |(min, max), corner| {
+            (min.min(*corner), max.max(*corner))
+        });
+    let min = min.max(vec3(0.0, 0.0, 0.0));
+    graphics::Rect::new(min.x, min.y, max.x - min.x, max.y - min.y)
+}
+
+/// egui textures always clamp to the edge, only the filters can be chosen
+#[inline]
+fn translate_texture_options(options: egui::TextureOptions) -> graphics::Sampler {
+    let translate_filter = |filter| match filter {
+        egui::TextureFilter::Nearest => graphics::FilterMode::Nearest,
+        egui::TextureFilter::Linear => graphics::FilterMode::Linear,
+    };
+    graphics::Sampler {
+        clamp_u: graphics::ClampMode::Clamp,
+        clamp_v: graphics::ClampMode::Clamp,
+        clamp_w: graphics::ClampMode::Clamp,
+        mag: translate_filter(options.magnification),
+        min: translate_filter(options.minification),
+    }
+}
+
 impl PixBuf {
     fn from_color(color: &egui::ColorImage) -> Self {
         let mut pix: Vec<u8> = Vec::with_capacity(color.pixels.len() * 4);
@@ -172,13 +401,121 @@ impl PixBuf {
         )
     }
 
-    fn blit(&mut self, pix: &PixBuf, pos: (usize, usize)) {
-        for row in pos.1..pos.1 + pix.h {
-            let dst = row * self.w + pos.0;
-            let src = row * pix.h;
-            for (i, j) in (dst..dst + pix.w).zip(src..src + pix.w) {
-                println!("{i} <- {j}");
+    /// Copies `pix` into this buffer with its top left corner at `pos`, the part that falls
+    /// outside is ignored, returns whether anything was copied
+    fn blit(&mut self, pix: &PixBuf, pos: (usize, usize)) -> bool {
+        if pos.0 >= self.w || pos.1 >= self.h {
+            eprintln!(
+                "Got update request outside of image: {}x{} at {:?} in {}x{}",
+                pix.w, pix.h, pos, self.w, self.h
+            );
+            return false;
+        }
+        let w = pix.w.min(self.w - pos.0);
+        let h = pix.h.min(self.h - pos.1);
+        for row in 0..h {
+            let dst = ((pos.1 + row) * self.w + pos.0) * 4;
+            let src = row * pix.w * 4;
+            self.pix[dst..dst + w * 4].copy_from_slice(&pix.pix[src..src + w * 4]);
+        }
+        w > 0 && h > 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A buffer whose pixels all have the value given by `fill` in their four channels
+    fn pixbuf(w: usize, h: usize, fill: impl Fn(usize, usize) -> u8) -> PixBuf {
+        let mut pix = Vec::with_capacity(w * h * 4);
+        for y in 0..h {
+            for x in 0..w {
+                pix.extend([fill(x, y); 4]);
             }
         }
+        PixBuf { pix, w, h }
+    }
+
+    /// A source whose pixels are all different, 11 for the top left one
+    fn source(w: usize, h: usize) -> PixBuf {
+        pixbuf(w, h, |x, y| (10 * (y + 1) + x + 1) as u8)
+    }
+
+    fn pixel(buf: &PixBuf, x: usize, y: usize) -> u8 {
+        let i = (y * buf.w + x) * 4;
+        assert!(buf.pix[i..i + 4].iter().all(|c| *c == buf.pix[i]));
+        buf.pix[i]
+    }
+
+    /// Checks every pixel of `buf`, the ones covered by `src` at `pos` must come from it
+    fn assert_blitted(buf: &PixBuf, src: &PixBuf, pos: (usize, usize)) {
+        for y in 0..buf.h {
+            for x in 0..buf.w {
+                let inside = x >= pos.0 && y >= pos.1 && x < pos.0 + src.w && y < pos.1 + src.h;
+                let expected = if inside {
+                    pixel(src, x - pos.0, y - pos.1)
+                } else {
+                    0
+                };
+                assert_eq!(pixel(buf, x, y), expected, "pixel ({x}, {y})");
+            }
+        }
+    }
+
+    #[test]
+    fn blit_at_origin() {
+        let mut buf = pixbuf(4, 4, |_, _| 0);
+        let src = source(2, 2);
+        assert!(buf.blit(&src, (0, 0)));
+        assert_blitted(&buf, &src, (0, 0));
+    }
+
+    #[test]
+    fn blit_touching_the_right_and_bottom_edges() {
+        let mut buf = pixbuf(4, 4, |_, _| 0);
+        let src = source(2, 2);
+        assert!(buf.blit(&src, (2, 2)));
+        assert_blitted(&buf, &src, (2, 2));
+    }
+
+    #[test]
+    fn blit_partly_outside_is_clipped() {
+        let mut buf = pixbuf(4, 4, |_, _| 0);
+        let src = source(3, 3);
+        assert!(buf.blit(&src, (2, 3)));
+        assert_eq!(pixel(&buf, 2, 3), 11);
+        assert_eq!(pixel(&buf, 3, 3), 12);
+        assert_eq!(pixel(&buf, 1, 3), 0);
+        assert_eq!(pixel(&buf, 2, 2), 0);
+        assert_eq!(buf.pix.len(), 4 * 4 * 4);
+    }
+
+    #[test]
+    fn blit_outside_is_rejected() {
+        let src = source(2, 2);
+        for pos in [(4, 0), (0, 4), (4, 4), (9, 1)] {
+            let mut buf = pixbuf(4, 4, |_, _| 0);
+            assert!(!buf.blit(&src, pos), "{pos:?}");
+            assert!(buf.pix.iter().all(|c| *c == 0));
+        }
+    }
+
+    #[test]
+    fn blit_non_square_sources() {
+        let mut buf = pixbuf(5, 4, |_, _| 0);
+        let wide = source(3, 1);
+        assert!(buf.blit(&wide, (1, 2)));
+        assert_blitted(&buf, &wide, (1, 2));
+
+        let mut buf = pixbuf(5, 4, |_, _| 0);
+        let tall = source(1, 3);
+        assert!(buf.blit(&tall, (4, 1)));
+        assert_blitted(&buf, &tall, (4, 1));
+
+        let mut buf = pixbuf(5, 4, |_, _| 0);
+        let src = source(3, 2);
+        assert!(buf.blit(&src, (1, 1)));
+        assert_blitted(&buf, &src, (1, 1));
     }
 }
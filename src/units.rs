@@ -0,0 +1,69 @@
+use ggez::graphics;
+
+/// Converts a position or a size in points of the ui to pixels
+#[inline]
+pub(crate) fn points_to_pixels(points: egui::Vec2, pixels_per_point: f32) -> egui::Vec2 {
+    points * pixels_per_point
+}
+
+/// Converts a position or a size in pixels to points of the ui
+#[inline]
+pub(crate) fn pixels_to_points(pixels: egui::Vec2, pixels_per_point: f32) -> egui::Vec2 {
+    pixels / pixels_per_point
+}
+
+/// Converts a rect in points of the ui to a rect in pixels
+pub(crate) fn rect_to_pixels(rect: egui::Rect, pixels_per_point: f32) -> graphics::Rect {
+    let min = points_to_pixels(rect.min.to_vec2(), pixels_per_point);
+    let size = points_to_pixels(rect.size(), pixels_per_point);
+    graphics::Rect::new(min.x, min.y, size.x, size.y)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use egui::{pos2, vec2};
+
+    #[test]
+    fn points_to_pixels_at_fractional_scale() {
+        assert_eq!(points_to_pixels(vec2(8.0, 4.0), 1.25), vec2(10.0, 5.0));
+        assert_eq!(points_to_pixels(vec2(8.0, 4.0), 2.0), vec2(16.0, 8.0));
+        assert_eq!(points_to_pixels(vec2(8.0, 4.0), 1.0), vec2(8.0, 4.0));
+    }
+
+    #[test]
+    fn pixels_to_points_at_fractional_scale() {
+        assert_eq!(pixels_to_points(vec2(10.0, 5.0), 1.25), vec2(8.0, 4.0));
+        assert_eq!(pixels_to_points(vec2(16.0, 8.0), 2.0), vec2(8.0, 4.0));
+        assert_eq!(
+            pixels_to_points(vec2(1920.0, 1080.0), 1.25),
+            vec2(1536.0, 864.0)
+        );
+    }
+
+    #[test]
+    fn round_trip() {
+        for pixels_per_point in [1.0, 1.25, 1.5, 2.0, 2.25] {
+            let pixels = vec2(333.0, 17.0);
+            let points = pixels_to_points(pixels, pixels_per_point);
+            let back = points_to_pixels(points, pixels_per_point);
+            assert!(
+                (back - pixels).length() < 1e-3,
+                "{pixels_per_point}: {back:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn rect_to_pixels_scales_position_and_size() {
+        let rect = egui::Rect::from_min_max(pos2(4.0, 8.0), pos2(12.0, 20.0));
+        assert_eq!(
+            rect_to_pixels(rect, 1.25),
+            graphics::Rect::new(5.0, 10.0, 10.0, 15.0)
+        );
+        assert_eq!(
+            rect_to_pixels(rect, 2.0),
+            graphics::Rect::new(8.0, 16.0, 16.0, 24.0)
+        );
+    }
+}
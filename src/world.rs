@@ -0,0 +1,104 @@
+use ggez::glam::Mat4;
+use ggez::graphics::{self, BlendMode, Canvas, DrawParam};
+use ggez::GameResult;
+
+use crate::units::pixels_to_points;
+use crate::{Clipboard, Gui, GuiContext, Input, OpenUrlHandler};
+
+/// A gui attached to something in the world, such as an in-game computer terminal
+///
+/// It has its own [`egui::Context`], [`Input`] and painter, it is drawn into an image of its own
+/// size which is then drawn on the canvas with an arbitrary transform. The pointer is mapped
+/// back through that transform, and it only reaches the gui while it is over the panel
+/// ```ignore
+/// let mut terminal = WorldGui::new(ctx, 320, 240);
+/// terminal.set_transform(Mat4::from_rotation_z(0.1) * Mat4::from_translation(vec3(100.0, 50.0, 0.0)));
+///
+/// // update
+/// egui::CentralPanel::default().show(&terminal.ctx(), |ui| ui.label("> _"));
+/// terminal.update(ctx);
+///
+/// // draw
+/// terminal.draw(ctx, &mut canvas)?;
+/// ```
+pub struct WorldGui {
+    gui: Gui,
+    image: graphics::Image,
+    transform: Mat4,
+    view: Mat4,
+}
+
+impl WorldGui {
+    /// Create a [`WorldGui`] whose panel is `width` by `height` pixels
+    pub fn new(ctx: &ggez::Context, width: u32, height: u32) -> Self {
+        let mut gui = Gui::default();
+        gui.set_target_size(Some((width, height)));
+        let image =
+            graphics::Image::new_canvas_image(ctx, ctx.gfx.surface_format(), width, height, 1);
+        Self {
+            gui,
+            image,
+            transform: Mat4::IDENTITY,
+            view: Mat4::IDENTITY,
+        }
+    }
+
+    /// Set the transform from the pixels of the panel to the coordinates of the canvas
+    pub fn set_transform(&mut self, transform: Mat4) {
+        self.transform = transform;
+    }
+
+    /// Set the transform from the coordinates of the canvas to the pixels of the window, only
+    /// needed for the pointer when the canvas has a camera or custom screen coordinates
+    pub fn set_view_transform(&mut self, view: Mat4) {
+        self.view = view;
+    }
+
+    pub fn input(&mut self) -> &mut Input {
+        &mut self.gui.input
+    }
+
+    /// The image the gui is drawn into
+    pub fn image(&self) -> &graphics::Image {
+        &self.image
+    }
+
+    /// Return an [`GuiContext`] for update the gui
+    pub fn ctx(&mut self) -> GuiContext {
+        self.gui.ctx()
+    }
+
+    /// Replace the [`Clipboard`] used for copy, cut and paste
+    pub fn set_clipboard(&mut self, clipboard: impl Clipboard + 'static) {
+        self.gui.set_clipboard(clipboard);
+    }
+
+    /// Replace the [`OpenUrlHandler`] called when egui asks to open a URL
+    pub fn set_open_url_handler(&mut self, open_url_handler: impl OpenUrlHandler + 'static) {
+        self.gui.set_open_url_handler(open_url_handler);
+    }
+
+    pub fn update(&mut self, ctx: &mut ggez::Context) {
+        let size = egui::vec2(self.image.width() as f32, self.image.height() as f32);
+        self.gui.input.transform = self.view * self.transform;
+        self.gui.input.pointer_bounds = Some(egui::Rect::from_min_size(
+            Default::default(),
+            pixels_to_points(size, self.gui.input.pixels_per_point()),
+        ));
+        self.gui.input.update(ctx);
+        self.gui.update_painter(ctx);
+        self.gui.update_platform(ctx);
+    }
+
+    /// Draws the gui into its image, and the image on the canvas with the transform
+    pub fn draw(&mut self, ctx: &mut ggez::Context, canvas: &mut Canvas) -> GameResult {
+        let image = self.image.clone();
+        self.gui.draw_to_image(ctx, &image)?;
+
+        let prev_blend = canvas.blend_mode();
+        canvas.set_blend_mode(BlendMode::PREMULTIPLIED);
+        canvas.draw(&self.image, DrawParam::default().transform(self.transform));
+        canvas.set_blend_mode(prev_blend);
+        Ok(())
+    }
+}